@@ -0,0 +1,349 @@
+use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+
+/// Maximum key length a [`ConfigStore`] record can hold
+pub const MAX_KEY_LEN: usize = 32;
+/// Maximum value length a [`ConfigStore`] record can hold
+pub const MAX_VAL_LEN: usize = 64;
+/// Maximum number of distinct keys a [`ConfigStore`] can compact at once
+pub const MAX_KEYS: usize = 16;
+
+const HEADER_LEN: usize = 4;
+const FOOTER_LEN: usize = 8;
+
+#[derive(Debug)]
+pub enum ConfigError<E> {
+    Storage(E),
+    /// `key`/`val` don't fit in [`MAX_KEY_LEN`]/[`MAX_VAL_LEN`]
+    TooLarge,
+    /// The caller-provided output buffer is smaller than the stored value
+    BufferTooSmall,
+    /// There's no room left to append a record, even after compaction
+    Full,
+    /// Compaction needs more than [`MAX_KEYS`] live records to track at once
+    TooManyKeys,
+}
+
+impl<E> From<E> for ConfigError<E> {
+    fn from(e: E) -> Self {
+        ConfigError::Storage(e)
+    }
+}
+
+/// A log-structured, wear-leveling key-value store built on an
+/// [`embedded_storage::nor_flash`] device.
+///
+/// Records are appended sequentially as `[key_len: u16][val_len: u16][key][val][seq: u32][crc32:
+/// u32]`. [`get`](Self::get) scans the whole log and returns the value of the highest-`seq`
+/// record whose CRC validates and whose key matches; [`set`](Self::set) appends a new record
+/// with `seq` one higher than the highest seen so far. When the log is full, [`set`](Self::set)
+/// compacts by keeping only the latest live record per key and rewriting them from the start,
+/// which spreads writes across the whole device instead of wearing out a single page.
+pub struct ConfigStore<S> {
+    storage: S,
+    write_cursor: u32,
+    next_seq: u32,
+}
+
+struct Record<'a> {
+    key: &'a [u8],
+    val: &'a [u8],
+    seq: u32,
+}
+
+#[derive(Clone, Copy)]
+struct LiveRecord {
+    key: [u8; MAX_KEY_LEN],
+    key_len: usize,
+    val: [u8; MAX_VAL_LEN],
+    val_len: usize,
+    seq: u32,
+}
+
+impl LiveRecord {
+    fn key(&self) -> &[u8] {
+        &self.key[..self.key_len]
+    }
+
+    fn val(&self) -> &[u8] {
+        &self.val[..self.val_len]
+    }
+
+    fn from_record(record: &Record) -> Self {
+        let mut key = [0u8; MAX_KEY_LEN];
+        key[..record.key.len()].copy_from_slice(record.key);
+        let mut val = [0u8; MAX_VAL_LEN];
+        val[..record.val.len()].copy_from_slice(record.val);
+        LiveRecord { key, key_len: record.key.len(), val, val_len: record.val.len(), seq: record.seq }
+    }
+}
+
+impl<S, E> ConfigStore<S>
+where
+    S: ReadNorFlash<Error = E> + NorFlash<Error = E>,
+{
+    /// Open a config store over `storage`, scanning the existing log (if any) to find the write
+    /// cursor and next sequence number
+    pub fn new(storage: S) -> Result<Self, ConfigError<E>> {
+        let mut me = ConfigStore { storage, write_cursor: 0, next_seq: 0 };
+        me.rescan()?;
+        Ok(me)
+    }
+
+    /// Look up `key`, copying its value into `value_out` and returning the number of bytes
+    /// written, or `None` if the key has never been set
+    pub fn get(&mut self, key: &[u8], value_out: &mut [u8]) -> Result<Option<usize>, ConfigError<E>> {
+        let mut best: Option<LiveRecord> = None;
+        self.for_each_record(|record| {
+            if record.key == key && best.map_or(true, |b| record.seq > b.seq) {
+                best = Some(LiveRecord::from_record(&record));
+            }
+        })?;
+
+        match best {
+            None => Ok(None),
+            Some(b) if b.val_len > value_out.len() => Err(ConfigError::BufferTooSmall),
+            Some(b) => {
+                value_out[..b.val_len].copy_from_slice(b.val());
+                Ok(Some(b.val_len))
+            }
+        }
+    }
+
+    /// Append a new record for `key`, compacting the log first if there isn't room
+    pub fn set(&mut self, key: &[u8], val: &[u8]) -> Result<(), ConfigError<E>> {
+        if key.len() > MAX_KEY_LEN || val.len() > MAX_VAL_LEN {
+            return Err(ConfigError::TooLarge);
+        }
+        let record_len = HEADER_LEN + key.len() + val.len() + FOOTER_LEN;
+        if self.write_cursor as usize + record_len > self.storage.capacity() {
+            self.compact()?;
+            if self.write_cursor as usize + record_len > self.storage.capacity() {
+                return Err(ConfigError::Full);
+            }
+        }
+        let seq = self.next_seq;
+        self.append(&Record { key, val, seq })?;
+        self.next_seq = seq + 1;
+        Ok(())
+    }
+
+    /// Rewrite the log keeping only the latest live record per key, reclaiming the space taken
+    /// by superseded records and spreading future writes back over the whole device
+    fn compact(&mut self) -> Result<(), ConfigError<E>> {
+        let mut live: [Option<LiveRecord>; MAX_KEYS] = [None; MAX_KEYS];
+        let mut overflowed = false;
+
+        self.for_each_record(|record| {
+            if let Some(slot) = live.iter_mut().flatten().find(|l| l.key() == record.key) {
+                if record.seq > slot.seq {
+                    *slot = LiveRecord::from_record(&record);
+                }
+            } else if let Some(slot) = live.iter_mut().find(|s| s.is_none()) {
+                *slot = Some(LiveRecord::from_record(&record));
+            } else {
+                overflowed = true;
+            }
+        })?;
+
+        if overflowed {
+            return Err(ConfigError::TooManyKeys);
+        }
+
+        self.storage.erase(0, self.storage.capacity() as u32)?;
+        self.write_cursor = 0;
+        for live_record in live.iter().flatten() {
+            self.append(&Record { key: live_record.key(), val: live_record.val(), seq: live_record.seq })?;
+        }
+        Ok(())
+    }
+
+    /// Recompute `write_cursor`/`next_seq` by scanning the whole log from the start
+    fn rescan(&mut self) -> Result<(), ConfigError<E>> {
+        let mut max_seq = None;
+        self.for_each_record(|record| {
+            max_seq = Some(max_seq.map_or(record.seq, |s: u32| s.max(record.seq)));
+        })?;
+        self.next_seq = max_seq.map_or(0, |s| s + 1);
+        Ok(())
+    }
+
+    /// Walk every valid record in the log in order, stopping at the first one whose lengths exceed
+    /// [`MAX_KEY_LEN`]/[`MAX_VAL_LEN`], whose lengths would run past capacity, or whose CRC fails
+    /// to validate (any of these marks end-of-log), and update
+    /// `write_cursor` to point just past the last valid record
+    fn for_each_record(&mut self, mut f: impl FnMut(Record)) -> Result<(), ConfigError<E>> {
+        let capacity = self.storage.capacity();
+        let mut offset = 0u32;
+        self.write_cursor = 0;
+        loop {
+            let mut header = [0u8; HEADER_LEN];
+            if offset as usize + HEADER_LEN > capacity {
+                break;
+            }
+            self.storage.read(offset, &mut header)?;
+            let key_len = u16::from_le_bytes([header[0], header[1]]) as usize;
+            let val_len = u16::from_le_bytes([header[2], header[3]]) as usize;
+            if key_len > MAX_KEY_LEN || val_len > MAX_VAL_LEN {
+                break;
+            }
+            let record_len = HEADER_LEN + key_len + val_len + FOOTER_LEN;
+            if offset as usize + record_len > capacity {
+                break;
+            }
+
+            let mut key = [0u8; MAX_KEY_LEN];
+            self.storage.read(offset + HEADER_LEN as u32, &mut key[..key_len])?;
+            let mut val = [0u8; MAX_VAL_LEN];
+            self.storage.read(offset + (HEADER_LEN + key_len) as u32, &mut val[..val_len])?;
+            let mut footer = [0u8; FOOTER_LEN];
+            self.storage.read(offset + (HEADER_LEN + key_len + val_len) as u32, &mut footer)?;
+            let seq = u32::from_le_bytes([footer[0], footer[1], footer[2], footer[3]]);
+            let stored_crc = u32::from_le_bytes([footer[4], footer[5], footer[6], footer[7]]);
+
+            let mut crc = crc32_init();
+            crc = crc32_update(crc, &header);
+            crc = crc32_update(crc, &key[..key_len]);
+            crc = crc32_update(crc, &val[..val_len]);
+            crc = crc32_update(crc, &seq.to_le_bytes());
+            if crc32_finish(crc) != stored_crc {
+                break;
+            }
+
+            f(Record { key: &key[..key_len], val: &val[..val_len], seq });
+            offset += record_len as u32;
+            self.write_cursor = offset;
+        }
+        Ok(())
+    }
+
+    /// Append one record at `write_cursor`, advancing it
+    fn append(&mut self, record: &Record) -> Result<(), ConfigError<E>> {
+        let mut header = [0u8; HEADER_LEN];
+        header[0..2].copy_from_slice(&(record.key.len() as u16).to_le_bytes());
+        header[2..4].copy_from_slice(&(record.val.len() as u16).to_le_bytes());
+
+        let mut crc = crc32_init();
+        crc = crc32_update(crc, &header);
+        crc = crc32_update(crc, record.key);
+        crc = crc32_update(crc, record.val);
+        crc = crc32_update(crc, &record.seq.to_le_bytes());
+        let crc = crc32_finish(crc);
+
+        let mut footer = [0u8; FOOTER_LEN];
+        footer[0..4].copy_from_slice(&record.seq.to_le_bytes());
+        footer[4..8].copy_from_slice(&crc.to_le_bytes());
+
+        let mut offset = self.write_cursor;
+        self.storage.write(offset, &header)?;
+        offset += HEADER_LEN as u32;
+        self.storage.write(offset, record.key)?;
+        offset += record.key.len() as u32;
+        self.storage.write(offset, record.val)?;
+        offset += record.val.len() as u32;
+        self.storage.write(offset, &footer)?;
+        offset += FOOTER_LEN as u32;
+
+        self.write_cursor = offset;
+        Ok(())
+    }
+}
+
+/// CRC-32 (IEEE 802.3), computed incrementally across a record's fields
+fn crc32_init() -> u32 {
+    0xFFFF_FFFF
+}
+
+fn crc32_update(mut crc: u32, bytes: &[u8]) -> u32 {
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    crc
+}
+
+fn crc32_finish(crc: u32) -> u32 {
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tiny in-memory stand-in for `Storage`, just big enough to force `ConfigStore` to
+    /// compact several times over the course of a test
+    struct MockFlash {
+        data: [u8; 256]
+    }
+
+    impl MockFlash {
+        fn new() -> Self {
+            MockFlash { data: [0xFF; 256] }
+        }
+    }
+
+    impl ReadNorFlash for MockFlash {
+        type Error = core::convert::Infallible;
+
+        const READ_SIZE: usize = 1;
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            bytes.copy_from_slice(&self.data[offset..offset + bytes.len()]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.data.len()
+        }
+    }
+
+    impl NorFlash for MockFlash {
+        const WRITE_SIZE: usize = 1;
+        const ERASE_SIZE: usize = 1;
+
+        fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+            self.data[from as usize..to as usize].fill(0xFF);
+            Ok(())
+        }
+
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            self.data[offset..offset + bytes.len()].copy_from_slice(bytes);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn compacts_when_the_log_fills_up() {
+        let mut store = ConfigStore::new(MockFlash::new()).unwrap();
+
+        // Together these 200 17-byte records overflow the 256 byte mock several times over, forcing
+        // `set` to compact the log (down to the one live record for "k") again and again.
+        for i in 0..200u32 {
+            store.set(b"k", &i.to_le_bytes()).unwrap();
+        }
+
+        let mut out = [0u8; 4];
+        let len = store.get(b"k", &mut out).unwrap().unwrap();
+        assert_eq!(&out[..len], &199u32.to_le_bytes());
+    }
+
+    #[test]
+    fn compaction_keeps_the_latest_value_per_key() {
+        let mut store = ConfigStore::new(MockFlash::new()).unwrap();
+
+        for i in 0..30u32 {
+            store.set(b"a", &i.to_le_bytes()).unwrap();
+            store.set(b"bb", &(i * 2).to_le_bytes()).unwrap();
+        }
+
+        let mut out = [0u8; 4];
+        let len = store.get(b"a", &mut out).unwrap().unwrap();
+        assert_eq!(&out[..len], &29u32.to_le_bytes());
+        let len = store.get(b"bb", &mut out).unwrap().unwrap();
+        assert_eq!(&out[..len], &58u32.to_le_bytes());
+    }
+}
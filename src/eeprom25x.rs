@@ -1,17 +1,75 @@
 extern crate embedded_hal;
 extern crate bit_field;
 
+use embedded_hal::blocking::delay::DelayUs;
 use embedded_hal::blocking::spi::{Transfer, Write};
 use embedded_hal::digital::v2::OutputPin;
 use crate::status::{Status, WriteProtection};
 use crate::register::{Instruction, Erase};
 use bit_field::BitField;
 
-pub struct Eeprom25x<SPI, CS, WP, HOLD> {
+/// Runtime description of a specific 25x part's geometry and capabilities.
+///
+/// This replaces the old `density_*`/`page_size_*`/part-number Cargo features: pass one of these
+/// to [`Eeprom25x::new`] to support any part at runtime, including firmware images that talk to
+/// more than one differently-sized part.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceConfig {
+    /// Total addressable size of the part, in bytes
+    pub capacity: usize,
+    /// Size of a single page that can be written with one `WRITE` command
+    pub page_size: usize,
+    /// Size of a single sector that can be erased with one `SectorErase` command
+    pub sector_size: usize,
+    /// Number of address bytes sent after an opcode (2 or 3 depending on the part's density)
+    pub address_bytes: u8,
+    /// Whether the part implements `DEEP_SLEEP_POWER_MODE` / `RELEASE_FROM_DEEP_SLEEP`
+    pub has_deep_sleep: bool,
+}
+
+impl DeviceConfig {
+    /// Build a `DeviceConfig` for a part not covered by the named constructors below
+    pub const fn new(capacity: usize, page_size: usize, sector_size: usize, address_bytes: u8, has_deep_sleep: bool) -> Self {
+        DeviceConfig { capacity, page_size, sector_size, address_bytes, has_deep_sleep }
+    }
+
+    /// Microchip 25LC512: 64 KiB, 128 byte pages, 1 KiB sectors, 2 address bytes, deep power-down capable
+    pub const fn _25lc512() -> Self {
+        Self::new(1024 * 64, 128, 1024, 2, true)
+    }
+
+    /// Microchip 25LC1024: 128 KiB, 256 byte pages, 2 KiB sectors, 3 address bytes, deep power-down capable
+    pub const fn _25lc1024() -> Self {
+        Self::new(1024 * 128, 256, 1024 * 2, 3, true)
+    }
+}
+
+/// How long the part needs to settle around deep power-down transitions, in microseconds.
+/// See the part's tDP (enter) / tRDP (exit) timings in its datasheet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeepPowerDownConfig {
+    /// Time to wait after `DeepSleepPowerMode` before the part is guaranteed to be asleep
+    pub enter_time: u32,
+    /// Time to wait after `ReleasePowerDown` before the manufacturer ID byte can be trusted
+    pub exit_time: u32,
+}
+
+/// A `DelayUs` that does nothing, for boards that don't need deep-sleep timing (or can't spare a
+/// timer for it). Pair with `deep_power_down: None` in [`Eeprom25x::new`]
+pub struct NoDelay;
+
+impl DelayUs<u32> for NoDelay {
+    fn delay_us(&mut self, _us: u32) {}
+}
+
+pub struct Eeprom25x<SPI, CS, WP, HOLD, DELAY> {
     spi: SPI,
     cs: CS,
     wp: WP,
-    hold: HOLD
+    hold: HOLD,
+    config: DeviceConfig,
+    delay: Option<DELAY>,
+    deep_power_down: Option<DeepPowerDownConfig>
 }
 
 #[derive(Debug)]
@@ -20,24 +78,38 @@ pub enum Error<SpiError, PinError> {
     PinError(PinError),
     BusyWriting,
     WrongId,
-    TooMuchData
+    TooMuchData,
+    /// An erase range wasn't aligned to the device's erase granularity, or ended before it started
+    NotAligned
 }
 
-impl<SPI, CS, WP, HOLD, SpiError, PinError> Eeprom25x<SPI, CS, WP, HOLD>
+impl<SPI, CS, WP, HOLD, DELAY, SpiError, PinError> Eeprom25x<SPI, CS, WP, HOLD, DELAY>
 where
     SPI: Transfer<u8, Error=SpiError> + Write<u8, Error=SpiError>,
     CS: OutputPin<Error = PinError>,
     WP: OutputPin<Error = PinError>,
-    HOLD: OutputPin<Error = PinError>
+    HOLD: OutputPin<Error = PinError>,
+    DELAY: DelayUs<u32>
 {
     /// Initializes the EEPROM device
     ///
     /// Checks if the manufacturer ID is correct otherwise returns an error.
     /// Makes sure that you can't write to the status register and also that the device is in deep
-    /// sleep mode. Also the chip hold is removed
-    pub fn new(spi: SPI, cs: CS, wp: WP, hold: HOLD) -> Result<Self, Error<SpiError, PinError>>{
+    /// sleep mode, if the part supports it. Also the chip hold is removed
+    ///
+    /// `deep_power_down` is only honored when `delay` is `Some`; pass `None` for both on boards
+    /// that don't need wake/sleep timing.
+    pub fn new(
+        spi: SPI,
+        cs: CS,
+        wp: WP,
+        hold: HOLD,
+        config: DeviceConfig,
+        delay: Option<DELAY>,
+        deep_power_down: Option<DeepPowerDownConfig>
+    ) -> Result<Self, Error<SpiError, PinError>>{
         let mut ret = Eeprom25x {
-            spi, cs, wp, hold
+            spi, cs, wp, hold, config, delay, deep_power_down
         };
         ret.cs.set_high().map_err(Error::PinError)?;
         ret.hold.set_high().map_err(Error::PinError)?;
@@ -51,16 +123,19 @@ where
             // ret.enable_write_to_status()?;
             // ret.write_enable()?;
             // ret.disable_write_to_status()?;
-            #[cfg(any(
-                feature = "25lc512",
-                feature = "25lc1024"
-            ))]
-            ret.deep_sleep()?;
+            if ret.config.has_deep_sleep {
+                ret.deep_sleep()?;
+            }
             ret.hold_transfer(false)?;
             Ok(ret)
         }
     }
 
+    /// The device geometry and capabilities this driver was constructed with
+    pub fn config(&self) -> DeviceConfig {
+        self.config
+    }
+
     /// Returns the status of the chip
     pub fn status(&mut self) -> Result<Status, Error<SpiError, PinError>> {
         let mut buf = [Instruction::ReadStatus as u8, 0];
@@ -96,7 +171,7 @@ where
     pub fn erase(&mut self, mut address: u32, erase: Erase) -> Result<(), Error<SpiError, PinError>> {
         self.error_on_writing()?;
         self.write_enable()?;
-        address.set_bits(24..31, erase as u32);
+        address.set_bits(24..32, erase as u32);
         let mut buf: [u8; 4] = address.to_be_bytes();
         self.transfer(&mut buf)
     }
@@ -111,32 +186,40 @@ where
     }
 
     /// Wake up the chip and also return the manufacturer ID
+    ///
+    /// If a delay and a [`DeepPowerDownConfig`] were supplied, `exit_time` is awaited *between*
+    /// sending `ReleasePowerDown` (+ dummy address bytes) and clocking out the manufacturer ID
+    /// byte, so the ID is only sampled once the part is actually ready, avoiding spurious
+    /// `WrongId` errors on cold wake.
     pub fn release_from_deep_sleep_and_get_manufacturer_id(&mut self) -> Result<u8, Error<SpiError, PinError>> {
-        
-        #[cfg(feature = "25lc1024")]
-        {
-            // <Instruction byte><Dummy address 3 bytes><Manufacturer ID byte>
-            let mut buf = [Instruction::ReleasePowerDown as u8, 0, 0, 0, 0];
-            self.transfer(&mut buf)?;
-            Ok(buf[4])
-        }
-        #[cfg(not(feature = "25lc1024"))]
-        {
-            // <Instruction byte><Dummy address 2 bytes><Manufacturer ID byte>
-            let mut buf = [Instruction::ReleasePowerDown as u8, 0, 0, 0];
-            self.transfer(&mut buf)?;
-            Ok(buf[3])
+        // <Instruction byte><Dummy address bytes>
+        let mut header = [0u8; 4];
+        header[0] = Instruction::ReleasePowerDown as u8;
+        let header_len = 1 + self.config.address_bytes as usize;
+
+        self.cs.set_low().map_err(Error::PinError)?;
+        self.spi.transfer(&mut header[..header_len]).map_err(Error::SpiError)?;
+        if let (Some(delay), Some(timing)) = (self.delay.as_mut(), self.deep_power_down) {
+            delay.delay_us(timing.exit_time);
         }
+        // <Manufacturer ID byte>
+        let mut id = [0u8; 1];
+        self.spi.transfer(&mut id).map_err(Error::SpiError)?;
+        self.cs.set_high().map_err(Error::PinError)?;
+        Ok(id[0])
     }
 
-    #[cfg(any(
-        feature = "25lc512",
-        feature = "25lc1024"
-    ))]
     /// Put the device in deep sleep mode
+    ///
+    /// If a delay and a [`DeepPowerDownConfig`] were supplied, blocks for `enter_time` before
+    /// returning, so the part is guaranteed asleep by the time this call completes.
     pub fn deep_sleep(&mut self) -> Result<(), Error<SpiError, PinError>>{
         let mut buf = [Instruction::DeepSleepPowerMode as u8];
-        self.transfer(&mut buf)
+        self.transfer(&mut buf)?;
+        if let (Some(delay), Some(timing)) = (self.delay.as_mut(), self.deep_power_down) {
+            delay.delay_us(timing.enter_time);
+        }
+        Ok(())
     }
 
     /// Disable writing to the status register
@@ -187,10 +270,9 @@ where
 
     pub fn read(&mut self, address: u32, bytes: &mut [u8]) -> Result<(), Error<SpiError, PinError>>
     {
-        let read_reg = e25x_read_from_address_command(address);
-        let read_reg: [u8; 4] = read_reg.to_be_bytes();
+        let (cmd, len) = e25x_command(Instruction::Read, address, self.config.address_bytes);
         self.cs.set_low().map_err(Error::PinError)?;
-        self.spi.write(&read_reg).map_err(Error::SpiError)?;
+        self.spi.write(&cmd[..len]).map_err(Error::SpiError)?;
         self.spi.transfer(bytes).map_err(Error::SpiError)?;
         self.cs.set_high().map_err(Error::PinError)?;
         Ok(())
@@ -198,26 +280,21 @@ where
 
     pub fn write(&mut self, address: u32, bytes: &[u8]) -> Result<(), Error<SpiError, PinError>>
     {
-        let read_reg = e25x_write_from_address_command(address);
-        let read_reg: [u8; 4] = read_reg.to_be_bytes();
+        let (cmd, len) = e25x_command(Instruction::Write, address, self.config.address_bytes);
         self.cs.set_low().map_err(Error::PinError)?;
-        self.spi.write(&read_reg).map_err(Error::SpiError)?;
+        self.spi.write(&cmd[..len]).map_err(Error::SpiError)?;
         self.spi.write(bytes).map_err(Error::SpiError)?;
         self.cs.set_high().map_err(Error::PinError)?;
         Ok(())
     }
 }
 
-/// Get a u32 command integer from a 24 bit address
-fn e25x_read_from_address_command(address: u32) -> u32 {
-    let mut ret = address;
-    ret.set_bits(24..31, Instruction::Read as u32);
-    ret
-}
-
-/// Get a u32 command integer from a 24 bit address
-fn e25x_write_from_address_command(address: u32) -> u32 {
-    let mut ret = address;
-    ret.set_bits(24..31, Instruction::Write as u32);
-    ret
+/// Build an opcode followed by `address_bytes` address bytes, big-endian, e.g. for `Read`/`Write`
+pub(crate) fn e25x_command(instruction: Instruction, address: u32, address_bytes: u8) -> ([u8; 4], usize) {
+    let mut buf = [0u8; 4];
+    buf[0] = instruction as u8;
+    let len = address_bytes as usize;
+    let address_be = address.to_be_bytes();
+    buf[1..1 + len].copy_from_slice(&address_be[4 - len..]);
+    (buf, 1 + len)
 }
@@ -0,0 +1,179 @@
+#![cfg(feature = "async")]
+
+extern crate embedded_hal_async;
+
+use bit_field::BitField;
+use embedded_hal::digital::v2::OutputPin;
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::spi::{Operation, SpiDevice};
+
+use crate::eeprom25x::{e25x_command, DeviceConfig, Error};
+use crate::register::{Erase, Instruction};
+use crate::status::{Status, WriteProtection};
+
+/// Async counterpart of [`Eeprom25x`](crate::eeprom25x::Eeprom25x), built on
+/// `embedded-hal-async`'s [`SpiDevice`], which owns chip-select for the duration of a
+/// transaction. Only `WP`/`HOLD` are driven directly here.
+pub struct Eeprom25xAsync<SPI, WP, HOLD, DELAY> {
+    spi: SPI,
+    wp: WP,
+    hold: HOLD,
+    delay: DELAY,
+    config: DeviceConfig
+}
+
+impl<SPI, WP, HOLD, DELAY, SpiError, PinError> Eeprom25xAsync<SPI, WP, HOLD, DELAY>
+where
+    SPI: SpiDevice<u8, Error = SpiError>,
+    WP: OutputPin<Error = PinError>,
+    HOLD: OutputPin<Error = PinError>,
+    DELAY: DelayNs
+{
+    /// Initializes the EEPROM device, see [`Eeprom25x::new`](crate::eeprom25x::Eeprom25x::new)
+    pub async fn new(spi: SPI, wp: WP, hold: HOLD, delay: DELAY, config: DeviceConfig) -> Result<Self, Error<SpiError, PinError>> {
+        let mut ret = Eeprom25xAsync { spi, wp, hold, delay, config };
+        ret.hold.set_high().map_err(Error::PinError)?;
+        ret.wp.set_high().map_err(Error::PinError)?;
+
+        let id = ret.release_from_deep_sleep_and_get_manufacturer_id().await?;
+        if id != 0x29 {
+            Err(Error::WrongId)
+        } else {
+            if ret.config.has_deep_sleep {
+                ret.deep_sleep().await?;
+            }
+            ret.hold_transfer(false)?;
+            Ok(ret)
+        }
+    }
+
+    /// The device geometry and capabilities this driver was constructed with
+    pub fn config(&self) -> DeviceConfig {
+        self.config
+    }
+
+    /// Returns the status of the chip
+    pub async fn status(&mut self) -> Result<Status, Error<SpiError, PinError>> {
+        let mut buf = [Instruction::ReadStatus as u8, 0];
+        self.spi.transfer_in_place(&mut buf).await.map_err(Error::SpiError)?;
+        Ok(Status { value: buf[1] })
+    }
+
+    /// Set the array write protection level, see
+    /// [`Eeprom25x::set_array_write_protection`](crate::eeprom25x::Eeprom25x::set_array_write_protection)
+    pub async fn set_array_write_protection(&mut self, level: WriteProtection) -> Result<(), Error<SpiError, PinError>> {
+        let mut status = self.status().await?;
+        status.set_write_protection_level(level);
+        self.write_enable().await?;
+        self.enable_write_to_status().await?;
+        self.write_enable().await?;
+        let mut buf = [Instruction::WriteStatus as u8, status.value];
+        self.spi.transfer_in_place(&mut buf).await.map_err(Error::SpiError)?;
+        self.disable_write_to_status().await
+    }
+
+    /// Returns the status of the chip or an error if it is busy writing
+    pub async fn error_on_writing(&mut self) -> Result<Status, Error<SpiError, PinError>> {
+        let status = self.status().await?;
+        if status.write_in_progress() {
+            Err(Error::BusyWriting)
+        } else {
+            Ok(status)
+        }
+    }
+
+    /// Returns whether a write cycle is currently in progress
+    pub async fn write_in_progress(&mut self) -> Result<bool, Error<SpiError, PinError>> {
+        Ok(self.status().await?.write_in_progress())
+    }
+
+    /// Poll the status register, awaiting a short delay between polls, until the write cycle
+    /// completes instead of busy-spinning
+    pub async fn wait_until_ready(&mut self) -> Result<(), Error<SpiError, PinError>> {
+        while self.write_in_progress().await? {
+            self.delay.delay_us(100).await;
+        }
+        Ok(())
+    }
+
+    /// Erase parts of the chip. Can be a page, a sector or the whole chip
+    pub async fn erase(&mut self, mut address: u32, erase: Erase) -> Result<(), Error<SpiError, PinError>> {
+        self.error_on_writing().await?;
+        self.write_enable().await?;
+        address.set_bits(24..32, erase as u32);
+        let mut buf: [u8; 4] = address.to_be_bytes();
+        self.spi.transfer_in_place(&mut buf).await.map_err(Error::SpiError)
+    }
+
+    /// Keep the device from clocking out data, or enable it to do so
+    pub fn hold_transfer(&mut self, enabled: bool) -> Result<(), Error<SpiError, PinError>> {
+        if enabled {
+            self.hold.set_high().map_err(Error::PinError)
+        } else {
+            self.hold.set_low().map_err(Error::PinError)
+        }
+    }
+
+    /// Wake up the chip and also return the manufacturer ID
+    pub async fn release_from_deep_sleep_and_get_manufacturer_id(&mut self) -> Result<u8, Error<SpiError, PinError>> {
+        // <Instruction byte><Dummy address bytes><Manufacturer ID byte>
+        let mut buf = [0u8; 5];
+        buf[0] = Instruction::ReleasePowerDown as u8;
+        let len = 1 + self.config.address_bytes as usize + 1;
+        self.spi.transfer_in_place(&mut buf[..len]).await.map_err(Error::SpiError)?;
+        Ok(buf[len - 1])
+    }
+
+    /// Put the device in deep sleep mode
+    pub async fn deep_sleep(&mut self) -> Result<(), Error<SpiError, PinError>> {
+        let mut buf = [Instruction::DeepSleepPowerMode as u8];
+        self.spi.write(&mut buf).await.map_err(Error::SpiError)
+    }
+
+    /// Disable writing to the status register
+    pub async fn disable_write_to_status(&mut self) -> Result<(), Error<SpiError, PinError>> {
+        self.wp.set_high().map_err(Error::PinError)?;
+        let mut status = self.status().await?;
+        status.set_write_protection_enabled(true);
+        let mut buf = [Instruction::WriteStatus as u8, status.value];
+        self.spi.transfer_in_place(&mut buf).await.map_err(Error::SpiError)?;
+        self.wp.set_low().map_err(Error::PinError)
+    }
+
+    /// Enable writing to the status register
+    pub async fn enable_write_to_status(&mut self) -> Result<(), Error<SpiError, PinError>> {
+        self.wp.set_high().map_err(Error::PinError)?;
+        let mut status = self.status().await?;
+        status.set_write_protection_enabled(false);
+        let mut buf = [Instruction::WriteStatus as u8, status.value];
+        self.spi.transfer_in_place(&mut buf).await.map_err(Error::SpiError)
+    }
+
+    /// Put the write protection down
+    pub async fn write_enable(&mut self) -> Result<(), Error<SpiError, PinError>> {
+        let mut buf = [Instruction::WriteEnable as u8];
+        self.spi.write(&mut buf).await.map_err(Error::SpiError)
+    }
+
+    /// Enable write protection
+    pub async fn write_disable(&mut self) -> Result<(), Error<SpiError, PinError>> {
+        let mut buf = [Instruction::WriteDisable as u8];
+        self.spi.write(&mut buf).await.map_err(Error::SpiError)
+    }
+
+    pub async fn read(&mut self, address: u32, bytes: &mut [u8]) -> Result<(), Error<SpiError, PinError>> {
+        let (cmd, len) = e25x_command(Instruction::Read, address, self.config.address_bytes);
+        self.spi
+            .transaction(&mut [Operation::Write(&cmd[..len]), Operation::Read(bytes)])
+            .await
+            .map_err(Error::SpiError)
+    }
+
+    pub async fn write(&mut self, address: u32, bytes: &[u8]) -> Result<(), Error<SpiError, PinError>> {
+        let (cmd, len) = e25x_command(Instruction::Write, address, self.config.address_bytes);
+        self.spi
+            .transaction(&mut [Operation::Write(&cmd[..len]), Operation::Write(bytes)])
+            .await
+            .map_err(Error::SpiError)
+    }
+}
@@ -1,12 +1,24 @@
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 #![recursion_limit = "1024"]
 
 pub mod eeprom25x;
 pub mod storage;
 pub mod status;
 pub mod register;
+pub mod config;
+pub mod stacked;
+#[cfg(feature = "async")]
+pub mod eeprom25x_async;
+#[cfg(feature = "async")]
+pub mod storage_async;
 
 pub use eeprom25x::*;
 pub use status::*;
 pub use storage::*;
-pub use register::*;
\ No newline at end of file
+pub use register::*;
+pub use config::*;
+pub use stacked::*;
+#[cfg(feature = "async")]
+pub use eeprom25x_async::*;
+#[cfg(feature = "async")]
+pub use storage_async::*;
\ No newline at end of file
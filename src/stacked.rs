@@ -0,0 +1,134 @@
+use core::cmp::min;
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::blocking::spi::{Transfer, Write};
+use embedded_hal::digital::v2::OutputPin;
+use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+use crate::eeprom25x::Error;
+use crate::storage::Storage;
+
+/// Presents `N` same-typed 25x chips on a shared SPI bus (with independent CS pins) as a single
+/// contiguous [`embedded_storage`] address space.
+///
+/// A global `offset` is translated into `(device_index, local_offset)` by walking the devices
+/// and subtracting each one's capacity in turn; a request that spans a device boundary is split
+/// into one page-aware operation per device it touches.
+pub struct StackedStorage<SPI, CS, WP, HOLD, DELAY, const N: usize> {
+    devices: [Storage<SPI, CS, WP, HOLD, DELAY>; N]
+}
+
+impl<SPI, CS, WP, HOLD, DELAY, SpiError, PinError, const N: usize> StackedStorage<SPI, CS, WP, HOLD, DELAY, N>
+where
+    SPI: Transfer<u8, Error=SpiError> + Write<u8, Error=SpiError>,
+    CS: OutputPin<Error = PinError>,
+    WP: OutputPin<Error = PinError>,
+    HOLD: OutputPin<Error = PinError>,
+    DELAY: DelayUs<u32>
+{
+    /// Create a new StackedStorage instance wrapping the given devices, in address order
+    pub fn new(devices: [Storage<SPI, CS, WP, HOLD, DELAY>; N]) -> Self {
+        StackedStorage { devices }
+    }
+
+    /// Translate a global offset into the device it falls in and the offset local to that device
+    fn locate(&self, offset: u32) -> Result<(usize, u32), Error<SpiError, PinError>> {
+        let mut remaining = offset as usize;
+        for (index, device) in self.devices.iter().enumerate() {
+            let capacity = device.capacity();
+            if remaining < capacity {
+                return Ok((index, remaining as u32));
+            }
+            remaining -= capacity;
+        }
+        Err(Error::TooMuchData)
+    }
+
+    /// Run `op` once per device a `[offset, offset + len)` range touches, each time with the
+    /// offset and length local to that device
+    fn split_at_device_boundaries(
+        &mut self,
+        offset: u32,
+        len: usize,
+        mut op: impl FnMut(&mut Storage<SPI, CS, WP, HOLD, DELAY>, u32, usize) -> Result<(), Error<SpiError, PinError>>
+    ) -> Result<(), Error<SpiError, PinError>> {
+        let (mut device_index, mut local_offset) = self.locate(offset)?;
+        let mut consumed = 0usize;
+        while consumed < len {
+            let device = &mut self.devices[device_index];
+            let remaining_in_device = device.capacity() - local_offset as usize;
+            let chunk_len = min(len - consumed, remaining_in_device);
+            op(device, local_offset, chunk_len)?;
+            consumed += chunk_len;
+            device_index += 1;
+            local_offset = 0;
+        }
+        Ok(())
+    }
+}
+
+impl<SPI, CS, WP, HOLD, DELAY, SpiError, PinError, const N: usize> ReadNorFlash for StackedStorage<SPI, CS, WP, HOLD, DELAY, N>
+where
+    SPI: Transfer<u8, Error=SpiError> + Write<u8, Error=SpiError>,
+    CS: OutputPin<Error = PinError>,
+    WP: OutputPin<Error = PinError>,
+    HOLD: OutputPin<Error = PinError>,
+    DELAY: DelayUs<u32>
+{
+    type Error = Error<SpiError, PinError>;
+
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        if offset as usize + bytes.len() > self.capacity() {
+            return Err(Error::TooMuchData);
+        }
+        let len = bytes.len();
+        let mut consumed = 0usize;
+        self.split_at_device_boundaries(offset, len, |device, local_offset, chunk_len| {
+            device.read(local_offset, &mut bytes[consumed..consumed + chunk_len])?;
+            consumed += chunk_len;
+            Ok(())
+        })
+    }
+
+    fn capacity(&self) -> usize {
+        self.devices.iter().map(|device| device.capacity()).sum()
+    }
+}
+
+impl<SPI, CS, WP, HOLD, DELAY, SpiError, PinError, const N: usize> NorFlash for StackedStorage<SPI, CS, WP, HOLD, DELAY, N>
+where
+    SPI: Transfer<u8, Error=SpiError> + Write<u8, Error=SpiError>,
+    CS: OutputPin<Error = PinError>,
+    WP: OutputPin<Error = PinError>,
+    HOLD: OutputPin<Error = PinError>,
+    DELAY: DelayUs<u32>
+{
+    const WRITE_SIZE: usize = 1;
+    const ERASE_SIZE: usize = 1;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        if to < from {
+            return Err(Error::NotAligned);
+        }
+        if to as usize > self.capacity() {
+            return Err(Error::TooMuchData);
+        }
+        let len = (to - from) as usize;
+        self.split_at_device_boundaries(from, len, |device, local_offset, chunk_len| {
+            device.erase(local_offset, local_offset + chunk_len as u32)
+        })
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        if offset as usize + bytes.len() > self.capacity() {
+            return Err(Error::TooMuchData);
+        }
+        let len = bytes.len();
+        let mut consumed = 0usize;
+        self.split_at_device_boundaries(offset, len, |device, local_offset, chunk_len| {
+            device.write(local_offset, &bytes[consumed..consumed + chunk_len])?;
+            consumed += chunk_len;
+            Ok(())
+        })
+    }
+}
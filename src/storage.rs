@@ -1,108 +1,155 @@
 use bit_field::BitField;
 use core::cmp::min;
 use crate::{eeprom25x::Eeprom25x,eeprom25x::Error};
+use crate::register::Erase;
+use embedded_hal::blocking::delay::DelayUs;
 use embedded_hal::blocking::spi::{Transfer, Write};
 use embedded_hal::digital::v2::OutputPin;
-use embedded_storage::ReadStorage;
+use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
 
 
-pub struct Storage<SPI, CS, WP, HOLD> 
+pub struct Storage<SPI, CS, WP, HOLD, DELAY>
 {
     /// Eeprom driver over which we implement the Storage traits
-    pub eeprom: Eeprom25x<SPI, CS, WP, HOLD>
+    pub eeprom: Eeprom25x<SPI, CS, WP, HOLD, DELAY>
 }
 
-impl<SPI, CS, WP, HOLD, SpiError, PinError> Storage<SPI, CS, WP, HOLD>
+impl<SPI, CS, WP, HOLD, DELAY, SpiError, PinError> Storage<SPI, CS, WP, HOLD, DELAY>
 where
     SPI: Transfer<u8, Error=SpiError> + Write<u8, Error=SpiError>,
     CS: OutputPin<Error = PinError>,
     WP: OutputPin<Error = PinError>,
-    HOLD: OutputPin<Error = PinError>
+    HOLD: OutputPin<Error = PinError>,
+    DELAY: DelayUs<u32>
 {
-    #[cfg(feature = "density_8k")]
-    const CAPACITY: usize = 1024 * 8;
-    #[cfg(feature = "density_16k")]
-    const CAPACITY: usize = 1024 * 16;
-    #[cfg(feature = "density_32k")]
-    const CAPACITY: usize = 1024 * 32;
-    #[cfg(feature = "density_64k")]
-    const CAPACITY: usize = 1024 * 64;
-    #[cfg(feature = "density_128k")]
-    const CAPACITY: usize = 1024 * 128;
-    #[cfg(feature = "density_256k")]
-    const CAPACITY: usize = 1024 * 256;
-    #[cfg(feature = "density_512k")]
-    const CAPACITY: usize = 1024 * 512;
-    #[cfg(feature = "density_1024k")]
-    const CAPACITY: usize = 1024 * 1024;
-
-    #[cfg(feature = "page_size_16")]
-    const PAGE_SIZE: usize = 16;
-    #[cfg(feature = "page_size_32")]
-    const PAGE_SIZE: usize = 32;
-    #[cfg(feature = "page_size_64")]
-    const PAGE_SIZE: usize = 64;
-    #[cfg(feature = "page_size_128")]
-    const PAGE_SIZE: usize = 128;
-    #[cfg(feature = "page_size_256")]
-    const PAGE_SIZE: usize = 256;
-
     /// Create a new Storage instance wrapping the given Eeprom
-    pub fn new(eeprom: Eeprom25x<SPI, CS, WP, HOLD>) -> Self {
+    pub fn new(eeprom: Eeprom25x<SPI, CS, WP, HOLD, DELAY>) -> Self {
         Storage { eeprom }
     }
 
+    fn page_size(&self) -> usize {
+        self.eeprom.config().page_size
+    }
+
+    fn sector_size(&self) -> usize {
+        self.eeprom.config().sector_size
+    }
+
+    fn wait_until_ready(&mut self) -> Result<(), Error<SpiError, PinError>> {
+        while self.eeprom.status_read()?.get_bit(0) {}
+        Ok(())
+    }
 }
 
-impl<SPI, CS, WP, HOLD, SpiError, PinError> embedded_storage::ReadStorage for Storage<SPI, CS, WP, HOLD>
+impl<SPI, CS, WP, HOLD, DELAY, SpiError, PinError> embedded_storage::nor_flash::ReadNorFlash for Storage<SPI, CS, WP, HOLD, DELAY>
 where
     SPI: Transfer<u8, Error=SpiError> + Write<u8, Error=SpiError>,
     CS: OutputPin<Error = PinError>,
     WP: OutputPin<Error = PinError>,
-    HOLD: OutputPin<Error = PinError>
+    HOLD: OutputPin<Error = PinError>,
+    DELAY: DelayUs<u32>
 {
     type Error = Error<SpiError, PinError>;
 
+    const READ_SIZE: usize = 1;
+
     fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        if offset as usize + bytes.len() > self.capacity() {
+            return Err(Error::TooMuchData);
+        }
+        let has_deep_sleep = self.eeprom.config().has_deep_sleep;
         self.eeprom.hold_transfer(true)?;
-        #[cfg(any(
-            feature = "25lc512",
-            feature = "25lc1024"
-        ))]
-        let _ = self.eeprom.release_from_deep_sleep_and_get_manufacturer_id()?;
+        if has_deep_sleep {
+            let _ = self.eeprom.release_from_deep_sleep_and_get_manufacturer_id()?;
+        }
         self.eeprom.read(offset, bytes)?;
-        #[cfg(any(
-            feature = "25lc512",
-            feature = "25lc1024"
-        ))]
-        self.eeprom.deep_sleep()?;
+        if has_deep_sleep {
+            self.eeprom.deep_sleep()?;
+        }
         self.eeprom.hold_transfer(false)?;
         Ok(())
     }
 
     fn capacity(&self) -> usize {
-        Self::CAPACITY
+        self.eeprom.config().capacity
     }
 }
 
-impl<SPI, CS, WP, HOLD, SpiError, PinError> embedded_storage::Storage for Storage<SPI, CS, WP, HOLD>
+impl<SPI, CS, WP, HOLD, DELAY, SpiError, PinError> embedded_storage::nor_flash::NorFlash for Storage<SPI, CS, WP, HOLD, DELAY>
 where
     SPI: Transfer<u8, Error=SpiError> + Write<u8, Error=SpiError>,
     CS: OutputPin<Error = PinError>,
     WP: OutputPin<Error = PinError>,
-    HOLD: OutputPin<Error = PinError>
+    HOLD: OutputPin<Error = PinError>,
+    DELAY: DelayUs<u32>
 {
+    // write() below splits arbitrary ranges into page-sized SPI transfers itself, so callers
+    // aren't required to align to the device's actual page size
+    const WRITE_SIZE: usize = 1;
+    // The real erase granularity (page/sector) is only known at runtime via `DeviceConfig` and
+    // can't be reported here, so this understates the true minimum: `erase()` still rejects any
+    // range that isn't aligned to the device's actual page size with `Error::NotAligned`, it just
+    // can't be caught ahead of time by a caller relying on `check_erase`/`ERASE_SIZE` alone.
+    const ERASE_SIZE: usize = 1;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        if to < from {
+            return Err(Error::NotAligned);
+        }
+        let capacity = self.capacity();
+        if to as usize > capacity {
+            return Err(Error::TooMuchData);
+        }
+        if from == 0 && to as usize == capacity {
+            let has_deep_sleep = self.eeprom.config().has_deep_sleep;
+            self.eeprom.hold_transfer(true)?;
+            if has_deep_sleep {
+                let _ = self.eeprom.release_from_deep_sleep_and_get_manufacturer_id()?;
+            }
+            self.eeprom.erase(0, Erase::ChipErase)?;
+            self.wait_until_ready()?;
+            if has_deep_sleep {
+                self.eeprom.deep_sleep()?;
+            }
+            return self.eeprom.hold_transfer(false);
+        }
+
+        let sector_size = self.sector_size() as u32;
+        let page_size = self.page_size() as u32;
+        let has_deep_sleep = self.eeprom.config().has_deep_sleep;
+        self.eeprom.hold_transfer(true)?;
+        if has_deep_sleep {
+            let _ = self.eeprom.release_from_deep_sleep_and_get_manufacturer_id()?;
+        }
+        let mut address = from;
+        while address < to {
+            if address % sector_size == 0 && to - address >= sector_size {
+                self.eeprom.erase(address, Erase::SectorErase)?;
+                address += sector_size;
+            } else if address % page_size == 0 && to - address >= page_size {
+                self.eeprom.erase(address, Erase::PageErase)?;
+                address += page_size;
+            } else {
+                return Err(Error::NotAligned);
+            }
+            self.wait_until_ready()?;
+        }
+        if has_deep_sleep {
+            self.eeprom.deep_sleep()?;
+        }
+        self.eeprom.hold_transfer(false)
+    }
+
     fn write(&mut self, mut offset: u32, mut bytes: &[u8]) -> Result<(), Self::Error> {
         if offset as usize + bytes.len() > self.capacity() {
             return Err(Error::TooMuchData);
         }
+        let has_deep_sleep = self.eeprom.config().has_deep_sleep;
         self.eeprom.hold_transfer(true)?;
-        let page_size = Self::PAGE_SIZE;
-        #[cfg(any(
-            feature = "25lc512",
-            feature = "25lc1024"
-        ))]
-        let _ = self.eeprom.release_from_deep_sleep_and_get_manufacturer_id()?;
+        let page_size = self.page_size();
+        if has_deep_sleep {
+            let _ = self.eeprom.release_from_deep_sleep_and_get_manufacturer_id()?;
+        }
         while !bytes.is_empty() {
             self.eeprom.write_enable()?;
             let this_page_offset = offset as usize % page_size;
@@ -111,14 +158,12 @@ where
             self.eeprom.write(offset, &bytes[..chunk_size])?;
             offset += chunk_size as u32;
             bytes = &bytes[chunk_size..];
-            while self.eeprom.status_read()?.get_bit(0) {}
+            self.wait_until_ready()?;
             self.eeprom.write_disable()?;
         }
-        #[cfg(any(
-            feature = "25lc512",
-            feature = "25lc1024"
-        ))]
-        self.eeprom.deep_sleep()?;
+        if has_deep_sleep {
+            self.eeprom.deep_sleep()?;
+        }
         self.eeprom.hold_transfer(false)?;
         Ok(())
     }
@@ -0,0 +1,167 @@
+#![cfg(feature = "async")]
+
+extern crate embedded_storage_async;
+
+use core::cmp::min;
+use embedded_hal::digital::v2::OutputPin;
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::spi::SpiDevice;
+use embedded_storage_async::nor_flash::{NorFlash, ReadNorFlash};
+
+use crate::eeprom25x::Error;
+use crate::eeprom25x_async::Eeprom25xAsync;
+use crate::register::Erase;
+
+/// Async counterpart of [`Storage`](crate::storage::Storage)
+pub struct StorageAsync<SPI, WP, HOLD, DELAY> {
+    /// Eeprom driver over which we implement the async NOR flash traits
+    pub eeprom: Eeprom25xAsync<SPI, WP, HOLD, DELAY>
+}
+
+impl<SPI, WP, HOLD, DELAY, SpiError, PinError> StorageAsync<SPI, WP, HOLD, DELAY>
+where
+    SPI: SpiDevice<u8, Error = SpiError>,
+    WP: OutputPin<Error = PinError>,
+    HOLD: OutputPin<Error = PinError>,
+    DELAY: DelayNs
+{
+    /// Create a new StorageAsync instance wrapping the given Eeprom
+    pub fn new(eeprom: Eeprom25xAsync<SPI, WP, HOLD, DELAY>) -> Self {
+        StorageAsync { eeprom }
+    }
+
+    fn page_size(&self) -> usize {
+        self.eeprom.config().page_size
+    }
+
+    fn sector_size(&self) -> usize {
+        self.eeprom.config().sector_size
+    }
+}
+
+impl<SPI, WP, HOLD, DELAY, SpiError, PinError> ReadNorFlash for StorageAsync<SPI, WP, HOLD, DELAY>
+where
+    SPI: SpiDevice<u8, Error = SpiError>,
+    WP: OutputPin<Error = PinError>,
+    HOLD: OutputPin<Error = PinError>,
+    DELAY: DelayNs
+{
+    type Error = Error<SpiError, PinError>;
+
+    const READ_SIZE: usize = 1;
+
+    async fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        if offset as usize + bytes.len() > self.capacity() {
+            return Err(Error::TooMuchData);
+        }
+        let has_deep_sleep = self.eeprom.config().has_deep_sleep;
+        self.eeprom.hold_transfer(true)?;
+        if has_deep_sleep {
+            let _ = self.eeprom.release_from_deep_sleep_and_get_manufacturer_id().await?;
+        }
+        self.eeprom.read(offset, bytes).await?;
+        if has_deep_sleep {
+            self.eeprom.deep_sleep().await?;
+        }
+        self.eeprom.hold_transfer(false)?;
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.eeprom.config().capacity
+    }
+}
+
+impl<SPI, WP, HOLD, DELAY, SpiError, PinError> NorFlash for StorageAsync<SPI, WP, HOLD, DELAY>
+where
+    SPI: SpiDevice<u8, Error = SpiError>,
+    WP: OutputPin<Error = PinError>,
+    HOLD: OutputPin<Error = PinError>,
+    DELAY: DelayNs
+{
+    // Our write() below splits arbitrary ranges into page-sized SPI transfers itself, so callers
+    // aren't required to align to the device's actual page size
+    const WRITE_SIZE: usize = 1;
+    // The real erase granularity (page/sector) is only known at runtime via `DeviceConfig` and
+    // can't be reported here, so this understates the true minimum: `erase()` still rejects any
+    // range that isn't aligned to the device's actual page size with `Error::NotAligned`, it just
+    // can't be caught ahead of time by a caller relying on `check_erase`/`ERASE_SIZE` alone.
+    const ERASE_SIZE: usize = 1;
+
+    async fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        if to < from {
+            return Err(Error::NotAligned);
+        }
+        let capacity = self.capacity();
+        if to as usize > capacity {
+            return Err(Error::TooMuchData);
+        }
+        if from == 0 && to as usize == capacity {
+            let has_deep_sleep = self.eeprom.config().has_deep_sleep;
+            self.eeprom.hold_transfer(true)?;
+            if has_deep_sleep {
+                let _ = self.eeprom.release_from_deep_sleep_and_get_manufacturer_id().await?;
+            }
+            self.eeprom.erase(0, Erase::ChipErase).await?;
+            self.eeprom.wait_until_ready().await?;
+            if has_deep_sleep {
+                self.eeprom.deep_sleep().await?;
+            }
+            return self.eeprom.hold_transfer(false);
+        }
+
+        let sector_size = self.sector_size() as u32;
+        let page_size = self.page_size() as u32;
+        let has_deep_sleep = self.eeprom.config().has_deep_sleep;
+        self.eeprom.hold_transfer(true)?;
+        if has_deep_sleep {
+            let _ = self.eeprom.release_from_deep_sleep_and_get_manufacturer_id().await?;
+        }
+        let mut address = from;
+        while address < to {
+            if address % sector_size == 0 && to - address >= sector_size {
+                self.eeprom.erase(address, Erase::SectorErase).await?;
+                address += sector_size;
+            } else if address % page_size == 0 && to - address >= page_size {
+                self.eeprom.erase(address, Erase::PageErase).await?;
+                address += page_size;
+            } else {
+                return Err(Error::NotAligned);
+            }
+            self.eeprom.wait_until_ready().await?;
+        }
+        if has_deep_sleep {
+            self.eeprom.deep_sleep().await?;
+        }
+        self.eeprom.hold_transfer(false)?;
+        Ok(())
+    }
+
+    async fn write(&mut self, mut offset: u32, mut bytes: &[u8]) -> Result<(), Self::Error> {
+        if offset as usize + bytes.len() > self.capacity() {
+            return Err(Error::TooMuchData);
+        }
+        let has_deep_sleep = self.eeprom.config().has_deep_sleep;
+        self.eeprom.hold_transfer(true)?;
+        let page_size = self.page_size();
+        if has_deep_sleep {
+            let _ = self.eeprom.release_from_deep_sleep_and_get_manufacturer_id().await?;
+        }
+        while !bytes.is_empty() {
+            self.eeprom.write_enable().await?;
+            let this_page_offset = offset as usize % page_size;
+            let this_page_remaining = page_size - this_page_offset;
+            let chunk_size = min(bytes.len(), this_page_remaining);
+            self.eeprom.write(offset, &bytes[..chunk_size]).await?;
+            offset += chunk_size as u32;
+            bytes = &bytes[chunk_size..];
+            self.eeprom.wait_until_ready().await?;
+            self.eeprom.write_disable().await?;
+        }
+        if has_deep_sleep {
+            self.eeprom.deep_sleep().await?;
+        }
+        self.eeprom.hold_transfer(false)?;
+        Ok(())
+    }
+}